@@ -0,0 +1,184 @@
+//! Replays a workload file against a running backend and reports latency
+//! percentiles and throughput per scenario.
+//! Run with: cargo run --bin bench -- workload.json [--api-base URL] [--report FILE] [--collector-url URL]
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Instant;
+
+#[derive(Debug, Deserialize)]
+struct Workload {
+    scenarios: Vec<Scenario>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Scenario {
+    name: String,
+    iterations: u32,
+    operations: Vec<Operation>,
+}
+
+/// One API call. `CreateWorkLog`/`CreateBalanceSnapshot` take the raw JSON
+/// body so a workload file doesn't need to track auto-assigned IDs itself.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Operation {
+    GetData,
+    GetWeather,
+    CreateWorkLog { body: serde_json::Value },
+    DeleteWorkLog { id: i32 },
+    CreateBalanceSnapshot { body: serde_json::Value },
+    DeleteBalanceSnapshot { id: i32 },
+}
+
+#[derive(Debug, Serialize)]
+struct ScenarioReport {
+    scenario: String,
+    iterations: u32,
+    operations: usize,
+    p50_ms: f64,
+    p95_ms: f64,
+    p99_ms: f64,
+    ops_per_sec: f64,
+}
+
+struct Args {
+    workload_path: PathBuf,
+    api_base: String,
+    report_path: Option<PathBuf>,
+    collector_url: Option<String>,
+}
+
+fn parse_args() -> Args {
+    let mut positional = Vec::new();
+    let mut api_base = "http://localhost:3000".to_string();
+    let mut report_path = None;
+    let mut collector_url = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--api-base" => {
+                if let Some(v) = args.next() {
+                    api_base = v;
+                }
+            }
+            "--report" => report_path = args.next().map(PathBuf::from),
+            "--collector-url" => collector_url = args.next(),
+            other => positional.push(other.to_string()),
+        }
+    }
+
+    let workload_path = positional.into_iter().next().map(PathBuf::from).unwrap_or_else(|| {
+        eprintln!("usage: bench <workload.json> [--api-base URL] [--report FILE] [--collector-url URL]");
+        std::process::exit(1);
+    });
+
+    Args { workload_path, api_base, report_path, collector_url }
+}
+
+async fn run_operation(client: &reqwest::Client, api_base: &str, op: &Operation) -> reqwest::Result<()> {
+    match op {
+        Operation::GetData => {
+            client.get(format!("{api_base}/api/data")).send().await?;
+        }
+        Operation::GetWeather => {
+            client.get(format!("{api_base}/api/weather")).send().await?;
+        }
+        Operation::CreateWorkLog { body } => {
+            client.post(format!("{api_base}/api/worklog")).json(body).send().await?;
+        }
+        Operation::DeleteWorkLog { id } => {
+            client.delete(format!("{api_base}/api/worklog/{id}")).send().await?;
+        }
+        Operation::CreateBalanceSnapshot { body } => {
+            client.post(format!("{api_base}/api/balance")).json(body).send().await?;
+        }
+        Operation::DeleteBalanceSnapshot { id } => {
+            client.delete(format!("{api_base}/api/balance/{id}")).send().await?;
+        }
+    }
+    Ok(())
+}
+
+fn percentile(sorted_ms: &[f64], p: f64) -> f64 {
+    if sorted_ms.is_empty() {
+        return 0.0;
+    }
+    let rank = (p * (sorted_ms.len() - 1) as f64).round() as usize;
+    sorted_ms[rank.min(sorted_ms.len() - 1)]
+}
+
+async fn run_scenario(client: &reqwest::Client, api_base: &str, scenario: &Scenario) -> ScenarioReport {
+    let mut latencies_ms = Vec::new();
+    let started = Instant::now();
+
+    for _ in 0..scenario.iterations {
+        for op in &scenario.operations {
+            let op_started = Instant::now();
+            if let Err(e) = run_operation(client, api_base, op).await {
+                eprintln!("operation failed in scenario {:?}: {}", scenario.name, e);
+                continue;
+            }
+            latencies_ms.push(op_started.elapsed().as_secs_f64() * 1000.0);
+        }
+    }
+
+    let elapsed_secs = started.elapsed().as_secs_f64();
+    latencies_ms.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    ScenarioReport {
+        scenario: scenario.name.clone(),
+        iterations: scenario.iterations,
+        operations: latencies_ms.len(),
+        p50_ms: percentile(&latencies_ms, 0.50),
+        p95_ms: percentile(&latencies_ms, 0.95),
+        p99_ms: percentile(&latencies_ms, 0.99),
+        ops_per_sec: if elapsed_secs > 0.0 { latencies_ms.len() as f64 / elapsed_secs } else { 0.0 },
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args = parse_args();
+
+    let content = std::fs::read_to_string(&args.workload_path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", args.workload_path.display(), e));
+    let workload: Workload = serde_json::from_str(&content)
+        .unwrap_or_else(|e| panic!("failed to parse {}: {}", args.workload_path.display(), e));
+
+    let client = reqwest::Client::new();
+    let mut reports = Vec::new();
+
+    for scenario in &workload.scenarios {
+        println!("Running scenario {:?} ({} iterations)...", scenario.name, scenario.iterations);
+        let report = run_scenario(&client, &args.api_base, scenario).await;
+        println!(
+            "  p50={:.1}ms p95={:.1}ms p99={:.1}ms ops/sec={:.1}",
+            report.p50_ms, report.p95_ms, report.p99_ms, report.ops_per_sec
+        );
+        reports.push(report);
+    }
+
+    let report_json = serde_json::to_string_pretty(&reports).unwrap();
+
+    if let Some(path) = &args.report_path {
+        std::fs::write(path, &report_json).expect("failed to write report file");
+        println!("Wrote report to {}", path.display());
+    } else {
+        println!("{}", report_json);
+    }
+
+    if let Some(url) = &args.collector_url {
+        let response = client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .body(report_json)
+            .send()
+            .await;
+        match response {
+            Ok(r) => println!("Posted report to {} -> {}", url, r.status()),
+            Err(e) => eprintln!("Failed to post report to {}: {}", url, e),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! At-rest encryption for the database file.
+//!
+//! Layout of an encrypted file: `magic || version || salt || nonce || ciphertext`
+//! (the AES-GCM authentication tag is appended to the ciphertext by the
+//! `aes-gcm` crate). The key is derived from a passphrase with Argon2id and
+//! a per-file random salt, so the same passphrase produces a different key
+//! each time the file is (re-)written.
+
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{bail, Context, Result};
+use argon2::Argon2;
+use rand::RngCore;
+use secrecy::{ExposeSecret, Secret};
+
+const MAGIC: &[u8; 4] = b"PFDE";
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+
+/// True if `data` starts with our magic bytes, i.e. it's an encrypted file
+/// rather than a plain SQLite database.
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.len() >= MAGIC.len() && &data[..MAGIC.len()] == MAGIC.as_slice()
+}
+
+fn derive_key(passphrase: &Secret<String>, salt: &[u8]) -> Result<Secret<[u8; KEY_LEN]>> {
+    let mut key = [0u8; KEY_LEN];
+    Argon2::default()
+        .hash_password_into(passphrase.expose_secret().as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("key derivation failed: {e}"))?;
+    Ok(Secret::new(key))
+}
+
+pub fn encrypt(passphrase: &Secret<String>, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt)?;
+    let cipher = Aes256Gcm::new_from_slice(key.expose_secret()).context("bad key length")?;
+
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext)
+        .map_err(|e| anyhow::anyhow!("encryption failed: {e}"))?;
+
+    let mut out = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce_bytes);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Decrypts a file produced by [`encrypt`]. Fails loudly (rather than
+/// silently returning garbage) if the passphrase is wrong or the file has
+/// been tampered with, since the GCM tag won't verify.
+pub fn decrypt(passphrase: &Secret<String>, data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < HEADER_LEN || !is_encrypted(data) {
+        bail!("not a recognized encrypted database file");
+    }
+
+    let version = data[MAGIC.len()];
+    if version != VERSION {
+        bail!("unsupported encrypted database version: {version}");
+    }
+
+    let salt = &data[MAGIC.len() + 1..MAGIC.len() + 1 + SALT_LEN];
+    let nonce_bytes = &data[MAGIC.len() + 1 + SALT_LEN..HEADER_LEN];
+    let ciphertext = &data[HEADER_LEN..];
+
+    let key = derive_key(passphrase, salt)?;
+    let cipher = Aes256Gcm::new_from_slice(key.expose_secret()).context("bad key length")?;
+    let nonce = Nonce::from_slice(nonce_bytes);
+
+    cipher
+        .decrypt(nonce, ciphertext)
+        .map_err(|_| anyhow::anyhow!("failed to decrypt database: wrong FINANCE_PASSPHRASE or the file has been tampered with"))
+}
@@ -0,0 +1,397 @@
+//! SQLite-backed storage for work logs and balance snapshots.
+//!
+//! Each mutation runs as its own transaction, so a crash mid-write can't
+//! corrupt existing data the way a full-file JSON rewrite could. IDs are
+//! assigned by SQLite (`AUTOINCREMENT`) rather than hand-tracked counters.
+
+use crate::crypto;
+use crate::types::{BalanceSnapshot, NewBalanceSnapshot, NewWorkLog, WorkLog};
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use secrecy::Secret;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tempfile::NamedTempFile;
+
+/// Embedded migrations, applied in order. Each is run once; the highest
+/// version applied is recorded in `schema_version`.
+const MIGRATIONS: &[(i64, &str)] = &[(1, include_str!("migrations/0001_init.sql"))];
+
+/// Env var holding the passphrase used to encrypt the database at rest.
+/// If unset, the database is kept in plaintext (backward compatible).
+const PASSPHRASE_ENV_VAR: &str = "FINANCE_PASSPHRASE";
+
+/// Errors a handler can turn into a specific HTTP status, rather than just
+/// panicking on every DB hiccup.
+#[derive(Debug)]
+pub enum DbError {
+    /// No row with the given id.
+    NotFound,
+    /// A `UNIQUE` constraint rejected the write (e.g. another balance
+    /// snapshot already has the date being written to).
+    Conflict,
+    /// Anything else - connection/IO/encryption failures.
+    Internal(anyhow::Error),
+}
+
+impl std::fmt::Display for DbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DbError::NotFound => write!(f, "not found"),
+            DbError::Conflict => write!(f, "conflicts with an existing row"),
+            DbError::Internal(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
+impl From<rusqlite::Error> for DbError {
+    fn from(e: rusqlite::Error) -> Self {
+        match &e {
+            rusqlite::Error::SqliteFailure(err, _)
+                if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+            {
+                DbError::Conflict
+            }
+            _ => DbError::Internal(e.into()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for DbError {
+    fn from(e: anyhow::Error) -> Self {
+        DbError::Internal(e)
+    }
+}
+
+pub struct Db {
+    conn: Mutex<Connection>,
+    /// Set when `FINANCE_PASSPHRASE` is present - the live `conn` then points
+    /// at a decrypted copy of `encrypted_path`, and every mutation
+    /// re-encrypts it back.
+    encryption: Option<Encryption>,
+}
+
+struct Encryption {
+    passphrase: Secret<String>,
+    /// The decrypted database SQLite actually has open: a securely-created
+    /// (0600, OS temp dir) temp file that's unlinked when this is dropped;
+    /// it is not removed if the process ends without dropping it.
+    live_file: NamedTempFile,
+    /// The at-rest encrypted file (what the caller passed to `open`).
+    encrypted_path: PathBuf,
+}
+
+fn run_migrations(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("CREATE TABLE IF NOT EXISTS schema_version (version INTEGER NOT NULL)")?;
+
+    let current: i64 = conn
+        .query_row("SELECT version FROM schema_version", [], |row| row.get(0))
+        .unwrap_or(0);
+
+    let mut latest = current;
+    for (version, sql) in MIGRATIONS {
+        if *version > current {
+            conn.execute_batch(sql)?;
+            latest = latest.max(*version);
+        }
+    }
+
+    if latest > current {
+        conn.execute("DELETE FROM schema_version", [])?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (?1)", params![latest])?;
+    }
+
+    Ok(())
+}
+
+fn row_to_work_log(row: &rusqlite::Row) -> rusqlite::Result<WorkLog> {
+    Ok(WorkLog {
+        id: row.get(0)?,
+        date: row.get(1)?,
+        job_id: row.get(2)?,
+        hours: row.get(3)?,
+        pay_rate: row.get(4)?,
+        tax_rate: row.get(5)?,
+        pay_cashed: row.get(6)?,
+    })
+}
+
+fn row_to_balance_snapshot(row: &rusqlite::Row) -> rusqlite::Result<BalanceSnapshot> {
+    Ok(BalanceSnapshot {
+        id: row.get(0)?,
+        date: row.get(1)?,
+        checking: row.get(2)?,
+        credit_available: row.get(3)?,
+        credit_limit: row.get(4)?,
+        personal_debt: row.get(5)?,
+        note: row.get(6)?,
+    })
+}
+
+impl Db {
+    /// Opens (creating if necessary) the database at `path`.
+    ///
+    /// If `FINANCE_PASSPHRASE` is set, `path` is treated as the at-rest
+    /// *encrypted* file: it's decrypted into a securely-created temp file
+    /// that SQLite actually opens, and every mutation re-encrypts that temp
+    /// file back over `path`. The temp file is unlinked when `Db` is dropped;
+    /// a process that ends without dropping it (killed by a signal, or
+    /// aborted) leaves the plaintext temp file behind.
+    /// Without the env var, `path` is opened directly as a plain SQLite
+    /// database, matching the old unencrypted behavior.
+    pub fn open(path: &Path) -> Result<Self> {
+        let passphrase = std::env::var(PASSPHRASE_ENV_VAR).ok().map(Secret::new);
+
+        let encryption = match passphrase {
+            Some(passphrase) => {
+                let mut live_file = NamedTempFile::with_prefix("finance-data-")
+                    .context("failed to create a secure temp file for the decrypted database")?;
+
+                if path.exists() {
+                    let encrypted = std::fs::read(path)
+                        .with_context(|| format!("failed to read {}", path.display()))?;
+                    let plaintext = crypto::decrypt(&passphrase, &encrypted).with_context(|| {
+                        format!("failed to decrypt {} - wrong {PASSPHRASE_ENV_VAR} or a tampered file", path.display())
+                    })?;
+                    live_file.write_all(&plaintext)?;
+                    live_file.flush()?;
+                }
+
+                Some(Encryption { passphrase, live_file, encrypted_path: path.to_path_buf() })
+            }
+            None => None,
+        };
+
+        let live_path = encryption.as_ref().map(|e| e.live_file.path()).unwrap_or(path);
+        let conn = Connection::open(live_path)?;
+        run_migrations(&conn)?;
+
+        let db = Self { conn: Mutex::new(conn), encryption };
+        db.persist_encrypted()?;
+        Ok(db)
+    }
+
+    /// Re-encrypts the live database back over the at-rest encrypted file.
+    /// A no-op when no passphrase was configured.
+    fn persist_encrypted(&self) -> Result<()> {
+        let Some(encryption) = &self.encryption else {
+            return Ok(());
+        };
+
+        // Hold the lock across the checkpoint *and* the read: other handlers
+        // share this `Db` behind an `Arc`, and reading the live file's bytes
+        // while another thread's `conn.execute` is mid-write would persist a
+        // torn, undecryptable snapshot.
+        let plaintext = {
+            let conn = self.conn.lock().unwrap();
+            conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE)")?;
+            std::fs::read(encryption.live_file.path())?
+        };
+
+        let ciphertext = crypto::encrypt(&encryption.passphrase, &plaintext)?;
+
+        // Write to a sibling temp file and rename it over the real path, so a
+        // crash mid-write leaves either the old file or the new one intact -
+        // never a truncated ciphertext that won't decrypt.
+        let dir = encryption.encrypted_path.parent().unwrap_or_else(|| Path::new("."));
+        let mut tmp = NamedTempFile::new_in(dir)
+            .context("failed to create a temp file for the encrypted database")?;
+        tmp.write_all(&ciphertext)?;
+        tmp.flush()?;
+        tmp.persist(&encryption.encrypted_path)
+            .map_err(|e| anyhow::Error::new(e.error))
+            .context("failed to persist encrypted database")?;
+
+        Ok(())
+    }
+
+    pub fn list_work_logs(&self) -> Result<Vec<WorkLog>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, date, job_id, hours, pay_rate, tax_rate, pay_cashed
+             FROM work_logs ORDER BY date",
+        )?;
+        Ok(stmt.query_map([], row_to_work_log)?.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn create_work_log(&self, new_log: &NewWorkLog) -> Result<WorkLog, DbError> {
+        let log = {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO work_logs (date, job_id, hours, pay_rate, tax_rate, pay_cashed)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    new_log.date,
+                    new_log.job_id,
+                    new_log.hours,
+                    new_log.pay_rate,
+                    new_log.tax_rate,
+                    new_log.pay_cashed,
+                ],
+            )?;
+            let id = i32::try_from(conn.last_insert_rowid())
+                .context("work log id overflowed i32 - schema needs a wider id column")?;
+            WorkLog {
+                id,
+                date: new_log.date.clone(),
+                job_id: new_log.job_id.clone(),
+                hours: new_log.hours,
+                pay_rate: new_log.pay_rate,
+                tax_rate: new_log.tax_rate,
+                pay_cashed: new_log.pay_cashed,
+            }
+        };
+        self.persist_encrypted()?;
+        Ok(log)
+    }
+
+    pub fn update_work_log(&self, id: i32, new_log: &NewWorkLog) -> Result<WorkLog, DbError> {
+        let updated = {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "UPDATE work_logs
+                 SET date = ?1, job_id = ?2, hours = ?3, pay_rate = ?4, tax_rate = ?5, pay_cashed = ?6
+                 WHERE id = ?7",
+                params![
+                    new_log.date,
+                    new_log.job_id,
+                    new_log.hours,
+                    new_log.pay_rate,
+                    new_log.tax_rate,
+                    new_log.pay_cashed,
+                    id,
+                ],
+            )?
+        };
+
+        if updated == 0 {
+            return Err(DbError::NotFound);
+        }
+
+        self.persist_encrypted()?;
+        Ok(WorkLog {
+            id,
+            date: new_log.date.clone(),
+            job_id: new_log.job_id.clone(),
+            hours: new_log.hours,
+            pay_rate: new_log.pay_rate,
+            tax_rate: new_log.tax_rate,
+            pay_cashed: new_log.pay_cashed,
+        })
+    }
+
+    pub fn delete_work_log(&self, id: i32) -> Result<(), DbError> {
+        let deleted = {
+            let conn = self.conn.lock().unwrap();
+            conn.execute("DELETE FROM work_logs WHERE id = ?1", params![id])?
+        };
+        if deleted == 0 {
+            return Err(DbError::NotFound);
+        }
+        self.persist_encrypted()?;
+        Ok(())
+    }
+
+    pub fn list_balance_snapshots(&self) -> Result<Vec<BalanceSnapshot>, DbError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, date, checking, credit_available, credit_limit, personal_debt, note
+             FROM balance_snapshots ORDER BY date",
+        )?;
+        Ok(stmt.query_map([], row_to_balance_snapshot)?.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Inserts a new snapshot, or overwrites the existing one for that date
+    /// (enforced by the `UNIQUE` index on `date`).
+    pub fn upsert_balance_snapshot(&self, new_snapshot: &NewBalanceSnapshot) -> Result<BalanceSnapshot, DbError> {
+        let snapshot = {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO balance_snapshots (date, checking, credit_available, credit_limit, personal_debt, note)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT(date) DO UPDATE SET
+                    checking = excluded.checking,
+                    credit_available = excluded.credit_available,
+                    credit_limit = excluded.credit_limit,
+                    personal_debt = excluded.personal_debt,
+                    note = excluded.note",
+                params![
+                    new_snapshot.date,
+                    new_snapshot.checking,
+                    new_snapshot.credit_available,
+                    new_snapshot.credit_limit,
+                    new_snapshot.personal_debt,
+                    new_snapshot.note,
+                ],
+            )?;
+
+            conn.query_row(
+                "SELECT id, date, checking, credit_available, credit_limit, personal_debt, note
+                 FROM balance_snapshots WHERE date = ?1",
+                params![new_snapshot.date],
+                row_to_balance_snapshot,
+            )?
+        };
+        self.persist_encrypted()?;
+        Ok(snapshot)
+    }
+
+    /// Updates a snapshot in place. Returns `DbError::Conflict` (rather than
+    /// panicking) if the new date collides with a *different* snapshot,
+    /// since `date` is `UNIQUE` - callers wanting overwrite-by-date
+    /// semantics should use [`Db::upsert_balance_snapshot`] instead.
+    pub fn update_balance_snapshot(
+        &self,
+        id: i32,
+        new_snapshot: &NewBalanceSnapshot,
+    ) -> Result<BalanceSnapshot, DbError> {
+        let updated = {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "UPDATE balance_snapshots
+                 SET date = ?1, checking = ?2, credit_available = ?3, credit_limit = ?4,
+                     personal_debt = ?5, note = ?6
+                 WHERE id = ?7",
+                params![
+                    new_snapshot.date,
+                    new_snapshot.checking,
+                    new_snapshot.credit_available,
+                    new_snapshot.credit_limit,
+                    new_snapshot.personal_debt,
+                    new_snapshot.note,
+                    id,
+                ],
+            )?
+        };
+
+        if updated == 0 {
+            return Err(DbError::NotFound);
+        }
+
+        self.persist_encrypted()?;
+        Ok(BalanceSnapshot {
+            id,
+            date: new_snapshot.date.clone(),
+            checking: new_snapshot.checking,
+            credit_available: new_snapshot.credit_available,
+            credit_limit: new_snapshot.credit_limit,
+            personal_debt: new_snapshot.personal_debt,
+            note: new_snapshot.note.clone(),
+        })
+    }
+
+    pub fn delete_balance_snapshot(&self, id: i32) -> Result<(), DbError> {
+        let deleted = {
+            let conn = self.conn.lock().unwrap();
+            conn.execute("DELETE FROM balance_snapshots WHERE id = ?1", params![id])?
+        };
+        if deleted == 0 {
+            return Err(DbError::NotFound);
+        }
+        self.persist_encrypted()?;
+        Ok(())
+    }
+}
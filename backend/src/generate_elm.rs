@@ -1,5 +1,13 @@
 //! Generates Elm types and decoders from Rust types.
 //! Run with: cargo run --bin generate-elm
+//!
+//! This only ever generates the `Api.Types` module - type definitions plus
+//! JSON encoders/decoders - never HTTP call wrappers, even for the
+//! pre-existing POST endpoints. The PUT endpoints for editing work logs and
+//! balance snapshots reuse the same `NewWorkLog`/`NewBalanceSnapshot` request
+//! bodies and `WorkLog`/`BalanceSnapshot` responses as the existing POST/GET
+//! endpoints, so no new type needs generating here; the Elm call sites build
+//! their own `Http.request`s (method, URL, body) around these encoders.
 
 mod types;
 
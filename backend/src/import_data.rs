@@ -0,0 +1,60 @@
+//! One-time importer that seeds the SQLite database from a legacy
+//! `data.json` file (the format written by the pre-SQLite `AppData::save`).
+//! Run with: cargo run --bin import-data -- [data.json] [data.db]
+
+mod crypto;
+mod db;
+mod types;
+
+use db::Db;
+use std::path::PathBuf;
+use types::FinanceData;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let json_path = args.next().map(PathBuf::from).unwrap_or_else(|| PathBuf::from("data.json"));
+    let db_path = args.next().map(PathBuf::from).unwrap_or_else(|| PathBuf::from("data.db"));
+
+    let content = std::fs::read_to_string(&json_path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", json_path.display(), e));
+    let data: FinanceData = serde_json::from_str(&content)
+        .unwrap_or_else(|e| panic!("failed to parse {}: {}", json_path.display(), e));
+
+    let db = Db::open(&db_path).unwrap_or_else(|e| panic!("failed to open {}: {}", db_path.display(), e));
+
+    let mut imported_logs = 0;
+    for log in &data.work_logs {
+        let new_log = types::NewWorkLog {
+            date: log.date.clone(),
+            job_id: log.job_id.clone(),
+            hours: log.hours,
+            pay_rate: log.pay_rate,
+            tax_rate: log.tax_rate,
+            pay_cashed: log.pay_cashed,
+        };
+        db.create_work_log(&new_log).expect("failed to import work log");
+        imported_logs += 1;
+    }
+
+    let mut imported_snapshots = 0;
+    for snapshot in &data.balance_snapshots {
+        let new_snapshot = types::NewBalanceSnapshot {
+            date: snapshot.date.clone(),
+            checking: snapshot.checking,
+            credit_available: snapshot.credit_available,
+            credit_limit: snapshot.credit_limit,
+            personal_debt: snapshot.personal_debt,
+            note: snapshot.note.clone(),
+        };
+        db.upsert_balance_snapshot(&new_snapshot).expect("failed to import balance snapshot");
+        imported_snapshots += 1;
+    }
+
+    println!(
+        "Imported {} work logs and {} balance snapshots from {} into {}",
+        imported_logs,
+        imported_snapshots,
+        json_path.display(),
+        db_path.display()
+    );
+}
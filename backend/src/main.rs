@@ -1,172 +1,121 @@
+mod crypto;
+mod db;
 mod types;
 
 use axum::{
     extract::{Path, State},
     http::{header, HeaderValue, StatusCode},
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Json, Router,
 };
+use db::Db;
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
-use std::{fs, path::PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tower_http::services::{ServeDir, ServeFile};
 use tower_http::set_header::SetResponseHeaderLayer;
 use types::{ApiResponse, BalanceSnapshot, FinanceData, Job, NewBalanceSnapshot, NewWorkLog, Weather, WorkLog};
 
-type AppState = Arc<RwLock<AppData>>;
+#[derive(Clone)]
+struct AppState {
+    db: Arc<Db>,
+    weather_cache: Arc<RwLock<Option<CachedWeather>>>,
+}
 
-struct AppData {
-    jobs: Vec<Job>,
-    work_logs: Vec<WorkLog>,
-    balance_snapshots: Vec<BalanceSnapshot>,
-    next_work_log_id: i32,
-    next_snapshot_id: i32,
-    data_file: PathBuf,
+#[derive(Clone)]
+struct CachedWeather {
+    fetched_at: Instant,
+    weather: Weather,
 }
 
-impl AppData {
-    fn load(data_file: PathBuf) -> Self {
-        let (work_logs, balance_snapshots): (Vec<WorkLog>, Vec<BalanceSnapshot>) = if data_file.exists() {
-            let content = fs::read_to_string(&data_file).unwrap_or_default();
-            if let Ok(data) = serde_json::from_str::<FinanceData>(&content) {
-                (data.work_logs, data.balance_snapshots)
-            } else {
-                (Vec::new(), Vec::new())
-            }
-        } else {
-            (Vec::new(), Vec::new())
-        };
-
-        let next_work_log_id = work_logs.iter().map(|w| w.id).max().unwrap_or(0) + 1;
-        let next_snapshot_id = balance_snapshots.iter().map(|s| s.id).max().unwrap_or(0) + 1;
-
-        // Hardcoded jobs
-        let jobs = vec![
-            Job { id: "alborn".to_string(), name: "Alborn".to_string() },
-            Job { id: "museum".to_string(), name: "Museum".to_string() },
-        ];
-
-        Self {
-            jobs,
-            work_logs,
-            balance_snapshots,
-            next_work_log_id,
-            next_snapshot_id,
-            data_file,
+/// Maps a storage-layer error to the HTTP status a handler should return,
+/// logging internal errors (which otherwise carry no context once reduced
+/// to a bare status code).
+fn db_error_status(e: &db::DbError) -> StatusCode {
+    match e {
+        db::DbError::NotFound => StatusCode::NOT_FOUND,
+        db::DbError::Conflict => StatusCode::CONFLICT,
+        db::DbError::Internal(err) => {
+            eprintln!("database error: {err}");
+            StatusCode::INTERNAL_SERVER_ERROR
         }
     }
+}
 
-    fn save(&self) {
-        let data = FinanceData {
-            jobs: self.jobs.clone(),
-            work_logs: self.work_logs.clone(),
-            balance_snapshots: self.balance_snapshots.clone(),
-        };
-        let content = serde_json::to_string_pretty(&data).unwrap();
-        fs::write(&self.data_file, content).ok();
-    }
+fn weather_ttl() -> Duration {
+    std::env::var("WEATHER_TTL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(600))
 }
 
-async fn get_data(State(state): State<AppState>) -> Json<FinanceData> {
-    let data = state.read().unwrap();
-    Json(FinanceData {
-        jobs: data.jobs.clone(),
-        work_logs: data.work_logs.clone(),
-        balance_snapshots: data.balance_snapshots.clone(),
-    })
+/// Jobs are hardcoded, not user-editable, so they don't get a table.
+fn jobs() -> Vec<Job> {
+    vec![
+        Job { id: "alborn".to_string(), name: "Alborn".to_string() },
+        Job { id: "museum".to_string(), name: "Museum".to_string() },
+    ]
+}
+
+async fn get_data(State(state): State<AppState>) -> Result<Json<FinanceData>, StatusCode> {
+    let work_logs = state.db.list_work_logs().map_err(|e| db_error_status(&e))?;
+    let balance_snapshots = state.db.list_balance_snapshots().map_err(|e| db_error_status(&e))?;
+    Ok(Json(FinanceData { jobs: jobs(), work_logs, balance_snapshots }))
 }
 
 async fn create_work_log(
     State(state): State<AppState>,
     Json(new_log): Json<NewWorkLog>,
-) -> (StatusCode, Json<ApiResponse>) {
-    let mut data = state.write().unwrap();
-
-    let log = WorkLog {
-        id: data.next_work_log_id,
-        date: new_log.date,
-        job_id: new_log.job_id,
-        hours: new_log.hours,
-        pay_rate: new_log.pay_rate,
-        tax_rate: new_log.tax_rate,
-        pay_cashed: new_log.pay_cashed,
-    };
-
-    data.next_work_log_id += 1;
-    data.work_logs.push(log);
-    data.work_logs.sort_by(|a, b| a.date.cmp(&b.date));
+) -> Result<(StatusCode, Json<ApiResponse>), StatusCode> {
+    state.db.create_work_log(&new_log).map_err(|e| db_error_status(&e))?;
+    Ok((StatusCode::OK, Json(ApiResponse { ok: true })))
+}
 
-    data.save();
-    (StatusCode::OK, Json(ApiResponse { ok: true }))
+async fn update_work_log(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(new_log): Json<NewWorkLog>,
+) -> Result<Json<WorkLog>, StatusCode> {
+    state.db.update_work_log(id, &new_log).map(Json).map_err(|e| db_error_status(&e))
 }
 
 async fn delete_work_log(
     State(state): State<AppState>,
     Path(id): Path<i32>,
-) -> (StatusCode, Json<ApiResponse>) {
-    let mut data = state.write().unwrap();
-
-    let original_len = data.work_logs.len();
-    data.work_logs.retain(|w| w.id != id);
-
-    if data.work_logs.len() == original_len {
-        return (StatusCode::NOT_FOUND, Json(ApiResponse { ok: false }));
-    }
-
-    data.save();
-    (StatusCode::OK, Json(ApiResponse { ok: true }))
+) -> Result<(StatusCode, Json<ApiResponse>), StatusCode> {
+    state.db.delete_work_log(id).map_err(|e| db_error_status(&e))?;
+    Ok((StatusCode::OK, Json(ApiResponse { ok: true })))
 }
 
 async fn create_balance_snapshot(
     State(state): State<AppState>,
     Json(new_snapshot): Json<NewBalanceSnapshot>,
-) -> (StatusCode, Json<ApiResponse>) {
-    let mut data = state.write().unwrap();
-
-    // Check if snapshot for this date already exists - if so, overwrite it
-    if let Some(existing) = data.balance_snapshots.iter_mut().find(|s| s.date == new_snapshot.date) {
-        existing.checking = new_snapshot.checking;
-        existing.credit_available = new_snapshot.credit_available;
-        existing.credit_limit = new_snapshot.credit_limit;
-        existing.personal_debt = new_snapshot.personal_debt;
-        existing.note = new_snapshot.note;
-    } else {
-        let snapshot = BalanceSnapshot {
-            id: data.next_snapshot_id,
-            date: new_snapshot.date,
-            checking: new_snapshot.checking,
-            credit_available: new_snapshot.credit_available,
-            credit_limit: new_snapshot.credit_limit,
-            personal_debt: new_snapshot.personal_debt,
-            note: new_snapshot.note,
-        };
-
-        data.next_snapshot_id += 1;
-        data.balance_snapshots.push(snapshot);
-        data.balance_snapshots.sort_by(|a, b| a.date.cmp(&b.date));
-    }
+) -> Result<(StatusCode, Json<ApiResponse>), StatusCode> {
+    state.db.upsert_balance_snapshot(&new_snapshot).map_err(|e| db_error_status(&e))?;
+    Ok((StatusCode::OK, Json(ApiResponse { ok: true })))
+}
 
-    data.save();
-    (StatusCode::OK, Json(ApiResponse { ok: true }))
+async fn update_balance_snapshot(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(new_snapshot): Json<NewBalanceSnapshot>,
+) -> Result<Json<BalanceSnapshot>, StatusCode> {
+    state.db.update_balance_snapshot(id, &new_snapshot).map(Json).map_err(|e| db_error_status(&e))
 }
 
 async fn delete_balance_snapshot(
     State(state): State<AppState>,
     Path(id): Path<i32>,
-) -> (StatusCode, Json<ApiResponse>) {
-    let mut data = state.write().unwrap();
-
-    let original_len = data.balance_snapshots.len();
-    data.balance_snapshots.retain(|s| s.id != id);
-
-    if data.balance_snapshots.len() == original_len {
-        return (StatusCode::NOT_FOUND, Json(ApiResponse { ok: false }));
-    }
-
-    data.save();
-    (StatusCode::OK, Json(ApiResponse { ok: true }))
+) -> Result<(StatusCode, Json<ApiResponse>), StatusCode> {
+    state.db.delete_balance_snapshot(id).map_err(|e| db_error_status(&e))?;
+    Ok((StatusCode::OK, Json(ApiResponse { ok: true })))
 }
 
-async fn get_weather() -> (StatusCode, Json<Weather>) {
+/// Fetches current weather for Anchorage from open-meteo. A fresh blocking
+/// round-trip, so callers should go through the TTL cache in `get_weather`
+/// rather than calling this directly on every request.
+async fn fetch_weather() -> Result<Weather, ()> {
     let lat = 61.2181;
     let lon = -149.9003;
 
@@ -175,21 +124,47 @@ async fn get_weather() -> (StatusCode, Json<Weather>) {
         lat, lon
     );
 
-    let response = match reqwest::get(&url).await {
-        Ok(r) => r,
-        Err(_) => return (StatusCode::SERVICE_UNAVAILABLE, Json(Weather { current_f: 0, high_f: 0, low_f: 0 })),
-    };
-
-    let json: serde_json::Value = match response.json().await {
-        Ok(j) => j,
-        Err(_) => return (StatusCode::SERVICE_UNAVAILABLE, Json(Weather { current_f: 0, high_f: 0, low_f: 0 })),
-    };
+    let response = reqwest::get(&url).await.map_err(|_| ())?;
+    let json: serde_json::Value = response.json().await.map_err(|_| ())?;
 
     let current = json["current_weather"]["temperature"].as_f64().unwrap_or(0.0) as i32;
     let high = json["daily"]["temperature_2m_max"][0].as_f64().unwrap_or(0.0) as i32;
     let low = json["daily"]["temperature_2m_min"][0].as_f64().unwrap_or(0.0) as i32;
+    let fetched_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+
+    Ok(Weather { current_f: current, high_f: high, low_f: low, fetched_at })
+}
+
+/// Serves weather from the TTL cache when it's fresh enough, otherwise
+/// refreshes from open-meteo. Falls back to the last known-good reading
+/// (rather than zeros) if the upstream call fails.
+async fn get_weather(State(state): State<AppState>) -> (StatusCode, Json<Weather>) {
+    {
+        let cache = state.weather_cache.read().unwrap();
+        if let Some(cached) = cache.as_ref() {
+            if cached.fetched_at.elapsed() < weather_ttl() {
+                return (StatusCode::OK, Json(cached.weather.clone()));
+            }
+        }
+    }
 
-    (StatusCode::OK, Json(Weather { current_f: current, high_f: high, low_f: low }))
+    match fetch_weather().await {
+        Ok(weather) => {
+            let mut cache = state.weather_cache.write().unwrap();
+            *cache = Some(CachedWeather { fetched_at: Instant::now(), weather: weather.clone() });
+            (StatusCode::OK, Json(weather))
+        }
+        Err(()) => {
+            let cache = state.weather_cache.read().unwrap();
+            match cache.as_ref() {
+                Some(cached) => (StatusCode::OK, Json(cached.weather.clone())),
+                None => (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(Weather { current_f: 0, high_f: 0, low_f: 0, fetched_at: 0 }),
+                ),
+            }
+        }
+    }
 }
 
 #[tokio::main]
@@ -199,16 +174,19 @@ async fn main() {
         .and_then(|p| p.parse().ok())
         .unwrap_or(3000);
 
-    let data_file = PathBuf::from("data.json");
+    let db_file = PathBuf::from("data.db");
 
-    let state: AppState = Arc::new(RwLock::new(AppData::load(data_file)));
+    let state = AppState {
+        db: Arc::new(Db::open(&db_file).expect("failed to open database")),
+        weather_cache: Arc::new(RwLock::new(None)),
+    };
 
     let api_routes = Router::new()
         .route("/data", get(get_data))
         .route("/worklog", post(create_work_log))
-        .route("/worklog/:id", delete(delete_work_log))
+        .route("/worklog/:id", put(update_work_log).delete(delete_work_log))
         .route("/balance", post(create_balance_snapshot))
-        .route("/balance/:id", delete(delete_balance_snapshot))
+        .route("/balance/:id", put(update_balance_snapshot).delete(delete_balance_snapshot))
         .route("/weather", get(get_weather));
 
     let serve_dir = ServeDir::new("dist")
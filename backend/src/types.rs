@@ -82,4 +82,7 @@ pub struct Weather {
     pub current_f: i32,
     pub high_f: i32,
     pub low_f: i32,
+    /// Unix timestamp (seconds) of when this reading was fetched from
+    /// open-meteo, so the frontend can show how stale a cached value is.
+    pub fetched_at: i64,
 }
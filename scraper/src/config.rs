@@ -0,0 +1,35 @@
+use serde::Deserialize;
+
+/// Which field of a balance snapshot a scraped value should be written to.
+/// Matches the numeric fields of `NewBalanceSnapshot` on the backend.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceField {
+    Checking,
+    CreditAvailable,
+    CreditLimit,
+    PersonalDebt,
+}
+
+/// One bank account to log into and scrape a balance from.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountConfig {
+    /// Human-readable name, used only in log output.
+    pub name: String,
+    pub login_url: String,
+    pub username: String,
+    pub password: String,
+    pub username_selector: String,
+    pub password_selector: String,
+    pub submit_selector: String,
+    pub balance_selector: String,
+    pub field: BalanceField,
+}
+
+/// Loads the list of accounts to scrape from a JSON config file.
+pub fn load_accounts(path: &std::path::Path) -> anyhow::Result<Vec<AccountConfig>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("failed to read account config at {}: {}", path.display(), e))?;
+    let accounts: Vec<AccountConfig> = serde_json::from_str(&content)?;
+    Ok(accounts)
+}
@@ -1,25 +1,253 @@
-use anyhow::Result;
+mod config;
+
+use anyhow::{Context, Result};
 use chromiumoxide::browser::{Browser, BrowserConfig};
+use chromiumoxide::cdp::browser_protocol::network::{Cookie, CookieParam};
+use config::{AccountConfig, BalanceField};
 use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Request body posted to the backend's `/api/balance` endpoint.
+/// Mirrors `backend::types::NewBalanceSnapshot` - the two binaries don't
+/// share a library crate, so the shape is kept in sync by hand.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NewBalanceSnapshot {
+    date: String,
+    checking: f64,
+    credit_available: f64,
+    credit_limit: f64,
+    personal_debt: f64,
+    note: String,
+}
+
+/// Just the fields of `backend::types::BalanceSnapshot` we need to seed a
+/// merge. Extra fields in the response (id, jobs, work logs, ...) are
+/// ignored by serde rather than declared here.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BalanceSnapshot {
+    date: String,
+    checking: f64,
+    credit_available: f64,
+    credit_limit: f64,
+    personal_debt: f64,
+    note: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FinanceData {
+    balance_snapshots: Vec<BalanceSnapshot>,
+}
+
+/// Fetches today's existing snapshot (if any) from the backend so scraped
+/// values can be merged into it instead of a blank one - `/api/balance`
+/// overwrites by date, so posting a blank snapshot would clobber every
+/// field no account maps to.
+async fn fetch_todays_snapshot(client: &reqwest::Client, api_base: &str, date: &str) -> Result<NewBalanceSnapshot> {
+    let url = format!("{}/api/data", api_base);
+    let data: FinanceData = client.get(&url).send().await?.json().await?;
+
+    Ok(match data.balance_snapshots.into_iter().find(|s| s.date == date) {
+        Some(s) => NewBalanceSnapshot {
+            date: s.date,
+            checking: s.checking,
+            credit_available: s.credit_available,
+            credit_limit: s.credit_limit,
+            personal_debt: s.personal_debt,
+            note: s.note,
+        },
+        None => NewBalanceSnapshot { date: date.to_string(), ..Default::default() },
+    })
+}
+
+struct Args {
+    config_path: PathBuf,
+    cookie_file: PathBuf,
+    api_base: String,
+    debug: bool,
+}
+
+fn parse_args() -> Args {
+    let mut config_path = PathBuf::from("accounts.json");
+    let mut cookie_file = PathBuf::from("cookies.json");
+    let mut api_base = "http://localhost:3000".to_string();
+    let mut debug = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--debug" => debug = true,
+            "--config" => {
+                if let Some(v) = args.next() {
+                    config_path = PathBuf::from(v);
+                }
+            }
+            "--cookie-file" => {
+                if let Some(v) = args.next() {
+                    cookie_file = PathBuf::from(v);
+                }
+            }
+            "--api-base" => {
+                if let Some(v) = args.next() {
+                    api_base = v;
+                }
+            }
+            other => eprintln!("Ignoring unknown argument: {}", other),
+        }
+    }
+
+    Args { config_path, cookie_file, api_base, debug }
+}
+
+/// Loads a previously saved cookie jar into the browser, if one exists.
+///
+/// `save_cookies` persists the CDP `Network.Cookie` shape `get_cookies`
+/// returns, but `set_cookies` takes `Network.CookieParam` - a different type
+/// with its own required fields - so the saved cookies are converted
+/// field-by-field rather than deserialized straight into whatever
+/// `set_cookies` expects and hoping the two line up.
+async fn load_cookies(browser: &Browser, cookie_file: &Path) -> Result<()> {
+    if !cookie_file.exists() {
+        return Ok(());
+    }
+    let content = std::fs::read_to_string(cookie_file)?;
+    let cookies: Vec<Cookie> = serde_json::from_str(&content)?;
+    let params = cookies
+        .into_iter()
+        .map(|c| {
+            CookieParam::builder()
+                .name(c.name)
+                .value(c.value)
+                .domain(c.domain)
+                .path(c.path)
+                .secure(c.secure)
+                .http_only(c.http_only)
+                .same_site(c.same_site)
+                .expires(c.expires)
+                .build()
+                .map_err(|e| anyhow::anyhow!(e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    browser.set_cookies(params).await?;
+    Ok(())
+}
+
+/// Persists the browser's current cookie jar so session/MFA state survives
+/// between runs.
+async fn save_cookies(browser: &Browser, cookie_file: &Path) -> Result<()> {
+    let cookies: Vec<Cookie> = browser.get_cookies().await?;
+    let content = serde_json::to_string_pretty(&cookies)?;
+    std::fs::write(cookie_file, content)?;
+    Ok(())
+}
+
+/// Parses a scraped balance string such as `"$1,234.56"`, `"-$50.00"`, or a
+/// parenthesized negative like `"($50.00)"` (common in bank UIs for
+/// debt/credit-used figures). Assumes US-style grouping (comma thousands,
+/// dot decimal) - the only convention any of this project's configured
+/// accounts has used - and errors out on anything with more than one `.` or
+/// with a `,` after the `.` rather than silently misreading non-US grouping
+/// (e.g. `"1.234,56"`) as a wrong number. A value with a single `.` and no
+/// comma after it, such as `"1.234"`, is always read US-style (1.234).
+fn parse_balance(text: &str) -> Result<f64> {
+    let trimmed = text.trim();
+    let negative = trimmed.starts_with('-') || (trimmed.starts_with('(') && trimmed.ends_with(')'));
+
+    let digits_and_dot: String = trimmed.chars().filter(|c| c.is_ascii_digit() || *c == '.').collect();
+    let comma_after_dot = match (trimmed.find('.'), trimmed.rfind(',')) {
+        (Some(dot), Some(comma)) => comma > dot,
+        _ => false,
+    };
+
+    anyhow::ensure!(
+        digits_and_dot.matches('.').count() <= 1 && !comma_after_dot,
+        "ambiguous balance format in {:?} - expected US-style comma-thousands/dot-decimal grouping",
+        text
+    );
+
+    let magnitude: f64 = digits_and_dot
+        .parse()
+        .with_context(|| format!("couldn't parse {:?} as a balance", text))?;
+
+    Ok(if negative { -magnitude } else { magnitude })
+}
+
+async fn scrape_account(browser: &Browser, account: &AccountConfig) -> Result<f64> {
+    println!("Logging in to {}...", account.name);
+
+    let page = browser.new_page(&account.login_url).await?;
+    page.wait_for_navigation().await?;
+
+    page.find_element(&account.username_selector)
+        .await
+        .with_context(|| format!("username field not found for {}", account.name))?
+        .click()
+        .await?
+        .type_str(&account.username)
+        .await?;
+
+    page.find_element(&account.password_selector)
+        .await
+        .with_context(|| format!("password field not found for {}", account.name))?
+        .click()
+        .await?
+        .type_str(&account.password)
+        .await?;
+
+    page.find_element(&account.submit_selector)
+        .await
+        .with_context(|| format!("submit button not found for {}", account.name))?
+        .click()
+        .await?;
+    page.wait_for_navigation().await?;
+
+    let balance_text = page
+        .find_element(&account.balance_selector)
+        .await
+        .with_context(|| format!("balance element not found for {}", account.name))?
+        .inner_text()
+        .await?
+        .unwrap_or_default();
+
+    let value = parse_balance(&balance_text)?;
+    println!("{}: {}", account.name, value);
+
+    page.close().await?;
+    Ok(value)
+}
+
+fn apply_field(snapshot: &mut NewBalanceSnapshot, field: &BalanceField, value: f64) {
+    match field {
+        BalanceField::Checking => snapshot.checking = value,
+        BalanceField::CreditAvailable => snapshot.credit_available = value,
+        BalanceField::CreditLimit => snapshot.credit_limit = value,
+        BalanceField::PersonalDebt => snapshot.personal_debt = value,
+    }
+}
+
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Get URL from command line args, default to a test page
-    let url = std::env::args().nth(1).unwrap_or_else(|| {
-        "https://example.com".to_string()
-    });
+    let args = parse_args();
 
-    println!("Starting browser...");
+    let accounts = config::load_accounts(&args.config_path)?;
 
-    // Launch browser
-    let (browser, mut handler) = Browser::launch(
-        BrowserConfig::builder()
-            .with_head() // Run with visible window for testing
-            .build()
-            .map_err(|e| anyhow::anyhow!("{}", e))?
-    ).await?;
+    let mut builder = BrowserConfig::builder();
+    if !args.debug {
+        builder = builder.headless_mode(chromiumoxide::browser::HeadlessMode::New);
+    } else {
+        builder = builder.with_head();
+    }
+    let browser_config = builder.build().map_err(|e| anyhow::anyhow!("{}", e))?;
+
+    let (browser, mut handler) = Browser::launch(browser_config).await?;
 
-    // Spawn handler
     let handle = tokio::spawn(async move {
         while let Some(event) = handler.next().await {
             if let Err(e) = event {
@@ -28,30 +256,42 @@ async fn main() -> Result<()> {
         }
     });
 
-    println!("Navigating to: {}", url);
-
-    // Create new page and navigate
-    let page = browser.new_page(&url).await?;
+    if let Err(e) = load_cookies(&browser, &args.cookie_file).await {
+        eprintln!("Couldn't load saved cookies, starting fresh: {}", e);
+    }
 
-    // Wait for page to load
-    page.wait_for_navigation().await?;
+    let client = reqwest::Client::new();
+    let date = today();
+    let mut snapshot = fetch_todays_snapshot(&client, &args.api_base, &date).await.unwrap_or_else(|e| {
+        eprintln!("Couldn't fetch today's existing snapshot, starting from blank: {:?}", e);
+        NewBalanceSnapshot { date: date.clone(), ..Default::default() }
+    });
 
-    // Get page title
-    let title = page.get_title().await?.unwrap_or_default();
-    println!("Page title: {}", title);
+    let mut scraped = 0;
+    for account in &accounts {
+        match scrape_account(&browser, account).await {
+            Ok(value) => {
+                apply_field(&mut snapshot, &account.field, value);
+                scraped += 1;
+            }
+            Err(e) => eprintln!("Failed to scrape {}: {:?}", account.name, e),
+        }
+    }
 
-    // Get page content (for debugging)
-    let content = page.content().await?;
-    println!("Page content length: {} chars", content.len());
+    if let Err(e) = save_cookies(&browser, &args.cookie_file).await {
+        eprintln!("Couldn't save cookies for next run: {:?}", e);
+    }
 
-    // Keep browser open for a moment so we can see it
-    println!("\nBrowser will close in 5 seconds...");
-    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+    if scraped == 0 {
+        eprintln!("No accounts scraped successfully, skipping POST so today's snapshot is left untouched.");
+    } else {
+        let url = format!("{}/api/balance", args.api_base);
+        let response = client.post(&url).json(&snapshot).send().await?;
+        println!("POST {} -> {}", url, response.status());
+    }
 
-    // Cleanup
     drop(browser);
     handle.abort();
 
-    println!("Done!");
     Ok(())
 }